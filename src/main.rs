@@ -1,13 +1,21 @@
 #[allow(unused_imports)]
 // supress warning for `dotenv().ok()` only being used in non-test code
 use dotenv::dotenv;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Duration, Utc};
 use reqwest::blocking::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json;
 use std::env;
+use std::io::{Read, Write};
+use std::net::TcpListener;
 use url::Url;
 use urlencoding::encode;
 
+/// Number of seconds before the real expiry at which we already treat the
+/// access token as stale, to avoid refreshing right as it dies mid-flight.
+const EXPIRY_SAFETY_MARGIN_SECS: i64 = 60;
+
 #[derive(Debug, PartialEq)]
 struct StravaConfig {
     client_id: u32,
@@ -15,9 +23,134 @@ struct StravaConfig {
     refresh_token: Option<String>,
     redirect_uri: String,
     access_token: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+    token_file: Option<String>,
     strava_url: String,
 }
 
+impl StravaConfig {
+    /// Whether the stored access token is missing or past its (margin-adjusted)
+    /// expiry and therefore needs refreshing.
+    fn is_token_stale(&self) -> bool {
+        match (&self.access_token, self.expires_at) {
+            (Some(_), Some(expires_at)) => {
+                Utc::now() >= expires_at - Duration::seconds(EXPIRY_SAFETY_MARGIN_SECS)
+            }
+            _ => true,
+        }
+    }
+
+    /// Return a copy of this config with the tokens/expiry from a freshly
+    /// obtained `RefreshResponse` applied.
+    fn with_tokens(&self, json: RefreshResponse) -> StravaConfig {
+        StravaConfig {
+            client_id: self.client_id,
+            client_secret: self.client_secret.clone(),
+            refresh_token: Some(json.refresh_token),
+            redirect_uri: self.redirect_uri.clone(),
+            access_token: Some(json.access_token),
+            expires_at: Some(json.expires_at),
+            token_file: self.token_file.clone(),
+            strava_url: self.strava_url.clone(),
+        }
+    }
+}
+
+/// On-disk token store. Strava rotates the refresh token on every refresh, so
+/// persisting the rotated credentials is what lets the tool survive across runs
+/// instead of re-reading a one-shot env value.
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenStore {
+    refresh_token: String,
+    access_token: String,
+    #[serde(with = "ts_seconds")]
+    expires_at: DateTime<Utc>,
+}
+
+/// Read a previously persisted token store, or `None` if the file is absent or
+/// unreadable (a missing store simply means we fall back to the env var).
+fn load_token_store(path: &str) -> Option<TokenStore> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Persist the freshly refreshed credentials back to the token file.
+fn save_token_store(path: &str, config: &StravaConfig) -> Result<(), Error> {
+    let store = TokenStore {
+        refresh_token: config.refresh_token.clone().unwrap_or_default(),
+        access_token: config.access_token.clone().unwrap_or_default(),
+        expires_at: config.expires_at.unwrap_or_else(Utc::now),
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&store)?)?;
+    Ok(())
+}
+
+/// Errors surfaced by the crate. Network and decoding failures carry their
+/// underlying cause; `StravaApi` carries the structured detail Strava returns
+/// on a rejected request so callers see *why* (invalid grant, rate limit, …).
+#[derive(Debug)]
+enum Error {
+    Reqwest(reqwest::Error),
+    Json(serde_json::Error),
+    StravaApi(StravaApiError),
+    Io(std::io::Error),
+    Url(url::ParseError),
+    Config(&'static str),
+}
+
+/// A non-200 response from Strava, with the error code/field pulled out of the
+/// structured `{"errors": [...]}` body and the raw JSON kept for inspection.
+#[derive(Debug)]
+struct StravaApiError {
+    status: reqwest::StatusCode,
+    code: String,
+    field: String,
+    value: serde_json::Value,
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Error {
+        Error::Reqwest(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Error {
+        Error::Json(error)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(error: url::ParseError) -> Error {
+        Error::Url(error)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Reqwest(e) => write!(f, "HTTP error: {}", e),
+            Error::Json(e) => write!(f, "JSON error: {}", e),
+            Error::StravaApi(e) => write!(
+                f,
+                "Strava API error ({}): {} ({})",
+                e.status, e.code, e.field
+            ),
+            Error::Io(e) => write!(f, "IO error: {}", e),
+            Error::Url(e) => write!(f, "URL parse error: {}", e),
+            Error::Config(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 struct RefreshResponse {
@@ -25,20 +158,175 @@ struct RefreshResponse {
     access_token: String,
     token_type: String,
     expires_in: u32,
+    #[serde(with = "ts_seconds")]
+    expires_at: DateTime<Utc>,
+}
+
+/// A single activity as returned by `GET /api/v3/athlete/activities`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[allow(dead_code)]
+struct Activity {
+    id: u64,
+    name: String,
+    distance: f64,
+    moving_time: u32,
+    #[serde(rename = "type")]
+    activity_type: String,
+    start_date: DateTime<Utc>,
+}
+
+/// Abstraction over the Strava HTTP API so the token logic can be exercised
+/// against a fake implementation in tests without standing up an HTTP mock,
+/// and so every call shares one configured client.
+trait StravaApi {
+    fn refresh_token(&self, config: &StravaConfig) -> Result<RefreshResponse, Error>;
+    fn exchange_token(&self, config: &StravaConfig, code: &str)
+        -> Result<RefreshResponse, Error>;
+    fn get(
+        &self,
+        method: &str,
+        access_token: &str,
+        params: &[(&str, &str)],
+    ) -> Result<serde_json::Value, Error>;
+}
+
+/// Turn a Strava response into its JSON body, mapping any non-200 status into a
+/// structured `StravaApiError` carrying the error code/field Strava reports.
+fn parse_response(response: reqwest::blocking::Response) -> Result<serde_json::Value, Error> {
+    let status = response.status();
+    let value: serde_json::Value = if status.is_success() {
+        response.json()?
+    } else {
+        // A non-JSON error body still yields a `StravaApiError`, with
+        // `code`/`field` falling back to "unknown".
+        response.json().unwrap_or(serde_json::Value::Null)
+    };
+    if status.is_success() {
+        Ok(value)
+    } else {
+        let code = value["errors"][0]["code"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string();
+        let field = value["errors"][0]["field"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string();
+        Err(Error::StravaApi(StravaApiError {
+            status,
+            code,
+            field,
+            value,
+        }))
+    }
+}
+
+/// Concrete `StravaApi` backed by a `reqwest` blocking client.
+struct StravaImpl {
+    client: Client,
+    base_url: String,
+    client_id: u32,
+    client_secret: String,
+}
+
+impl StravaImpl {
+    fn new(config: &StravaConfig) -> StravaImpl {
+        StravaImpl {
+            client: Client::new(),
+            base_url: config.strava_url.clone(),
+            client_id: config.client_id,
+            client_secret: config.client_secret.clone(),
+        }
+    }
+}
+
+impl StravaApi for StravaImpl {
+    fn refresh_token(&self, config: &StravaConfig) -> Result<RefreshResponse, Error> {
+        let url = Url::parse(format!("{}/oauth/token", self.base_url).as_str())?;
+        let refresh_token = config
+            .refresh_token
+            .clone()
+            .ok_or(Error::Config("STRAVA_REFRESH_TOKEN not set"))?;
+        let data = [
+            ("client_id", self.client_id.to_string()),
+            ("client_secret", self.client_secret.clone()),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token".to_string()),
+        ];
+
+        let response = self.client.post(url).form(&data).send()?;
+        let value = parse_response(response)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn exchange_token(
+        &self,
+        _config: &StravaConfig,
+        code: &str,
+    ) -> Result<RefreshResponse, Error> {
+        let url = Url::parse(format!("{}/oauth/token", self.base_url).as_str())?;
+        let data = [
+            ("client_id", self.client_id.to_string()),
+            ("client_secret", self.client_secret.clone()),
+            ("code", code.to_string()),
+            ("grant_type", "authorization_code".to_string()),
+        ];
+
+        let response = self.client.post(url).form(&data).send()?;
+        let value = parse_response(response)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn get(
+        &self,
+        method: &str,
+        access_token: &str,
+        params: &[(&str, &str)],
+    ) -> Result<serde_json::Value, Error> {
+        let url = Url::parse(format!("{}{}", self.base_url, method).as_str())?;
+
+        let response = self
+            .client
+            .get(url)
+            .bearer_auth(access_token)
+            .query(params)
+            .send()?;
+        parse_response(response)
+    }
 }
 
 fn main() {
-    let config = load_env_variables().unwrap();
-    println!("{:?}", config);
-    if config.refresh_token.is_none() {
-        let auth_url = build_auth_url(&config);
-        println!("{}", auth_url);
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let config = load_env_variables()?;
+    let api = StravaImpl::new(&config);
+    let config = if config.refresh_token.is_none() {
+        bootstrap(&api, &config)?
+    } else {
+        config
+    };
+    let config = if config.is_token_stale() {
+        refresh_strava_token(&api, &config)?
+    } else {
+        config
+    };
+
+    if let Some(access_token) = &config.access_token {
+        let activities = get_activities(&api, access_token, 1, 30)?;
+        println!("Fetched {} activities", activities.len());
+        for activity in &activities {
+            println!("{:?}", activity);
+        }
     }
-    let new_config = refresh_strava_token(&config);
-    println!("{:?}", new_config);
+    Ok(())
 }
 
-fn load_env_variables() -> Result<StravaConfig, &'static str> {
+fn load_env_variables() -> Result<StravaConfig, Error> {
     #[cfg(not(test))] // Only load .env variables if we are not running tests
     {
         dotenv().ok(); // Load .env variables
@@ -47,31 +335,47 @@ fn load_env_variables() -> Result<StravaConfig, &'static str> {
     let client_id: u32 = match env::var("STRAVA_CLIENT_ID") {
         Ok(value) => value
             .parse::<u32>()
-            .map_err(|_| "Invalid STRAVA_CLIENT_ID")?,
-        Err(_) => return Err("STRAVA_CLIENT_ID not set"),
+            .map_err(|_| Error::Config("Invalid STRAVA_CLIENT_ID"))?,
+        Err(_) => return Err(Error::Config("STRAVA_CLIENT_ID not set")),
     };
 
     let client_secret = match env::var("STRAVA_CLIENT_SECRET") {
         Ok(value) => value,
-        Err(_) => return Err("STRAVA_CLIENT_SECRET not set"),
+        Err(_) => return Err(Error::Config("STRAVA_CLIENT_SECRET not set")),
     };
 
-    let refresh_token: Option<String> = match env::var("STRAVA_REFRESH_TOKEN") {
+    let mut refresh_token: Option<String> = match env::var("STRAVA_REFRESH_TOKEN") {
         Ok(value) => Some(value),
         Err(_) => None,
     };
 
     let redirect_uri = match env::var("STRAVA_REDIRECT_URI") {
         Ok(value) => value,
-        Err(_) => "http://localhost/".to_string(),
+        // A non-privileged port so the callback listener can bind without root.
+        Err(_) => "http://localhost:8080/".to_string(),
     };
 
+    // A persisted store, when present, holds the most recently rotated
+    // credentials and takes precedence over the (possibly stale) env var.
+    let token_file = env::var("STRAVA_TOKEN_FILE").ok();
+    let mut access_token: Option<String> = None;
+    let mut expires_at: Option<DateTime<Utc>> = None;
+    if let Some(path) = &token_file {
+        if let Some(store) = load_token_store(path) {
+            refresh_token = Some(store.refresh_token);
+            access_token = Some(store.access_token);
+            expires_at = Some(store.expires_at);
+        }
+    }
+
     Ok(StravaConfig {
         client_id,
         client_secret,
         refresh_token,
         redirect_uri,
-        access_token: None,
+        access_token,
+        expires_at,
+        token_file,
         strava_url: "https://www.strava.com".to_string(),
     })
 }
@@ -81,43 +385,177 @@ fn build_auth_url(config: &StravaConfig) -> String {
     format!("https://www.strava.com/oauth/authorize?client_id={}&redirect_uri={}&response_type=code&scope=read,activity:read,activity:write", &config.client_id, encoded_redirect_uri)
 }
 
-fn refresh_strava_token(config: &StravaConfig) -> StravaConfig {
-    let url = match Url::parse(format!("{}/oauth/token", config.strava_url).as_str()) {
-        Ok(url) => url,
-        Err(e) => panic!("Failed to parse Strava URL: {}", e),
-    };
-    let data = [
-        ("client_id", config.client_id.to_string()),
-        ("client_secret", config.client_secret.clone()),
-        ("refresh_token", config.refresh_token.clone().unwrap()),
-        ("grant_type", "refresh_token".to_string()),
-    ];
-
-    let client = Client::new();
-    let response = match client.post(url).form(&data).send() {
-        Ok(response) => response,
-        Err(e) => panic!("Failed to send request: {}", e),
+/// Run the first-time onboarding flow: print the authorize URL, listen on the
+/// `redirect_uri` host/port for Strava's `?code=...` redirect, then exchange
+/// that code for a full token set.
+fn bootstrap(api: &dyn StravaApi, config: &StravaConfig) -> Result<StravaConfig, Error> {
+    let auth_url = build_auth_url(config);
+    println!("Open this URL in your browser to authorize the app:\n{}", auth_url);
+
+    let code = capture_auth_code(&config.redirect_uri)?;
+    let json = api.exchange_token(config, &code)?;
+    let new_config = config.with_tokens(json);
+    if let Some(path) = &new_config.token_file {
+        save_token_store(path, &new_config)?;
+    }
+    Ok(new_config)
+}
+
+/// Block on a one-shot HTTP listener bound to the redirect URI and return the
+/// `code` query parameter from Strava's redirect.
+fn capture_auth_code(redirect_uri: &str) -> Result<String, Error> {
+    let url = Url::parse(redirect_uri).map_err(|_| Error::Config("Invalid STRAVA_REDIRECT_URI"))?;
+    let host = url.host_str().unwrap_or("localhost").to_string();
+    // Require an explicit port: binding the scheme default (80) needs root, and
+    // silently substituting 8080 would listen on a port Strava never redirects
+    // to. The built-in default redirect URI carries `:8080` for this reason.
+    let port = url
+        .port()
+        .ok_or(Error::Config("STRAVA_REDIRECT_URI must include an explicit port"))?;
+
+    let listener = TcpListener::bind((host.as_str(), port))?;
+    println!("Waiting for Strava to redirect to {} ...", redirect_uri);
+
+    // A single redirect ends the flow: either Strava sent a `code` (success) or
+    // it sent an `error` / nothing (the user denied access). Looping past a
+    // code-less redirect would hang the tool forever.
+    let stream = listener
+        .incoming()
+        .next()
+        .ok_or(Error::Config("No authorization redirect received"))??;
+    let mut stream = stream;
+    let mut buffer = [0u8; 2048];
+    let read = stream.read(&mut buffer)?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("");
+    let code = extract_query_param(path, "code");
+
+    let message = match code {
+        Some(_) => "Authorization complete. You can close this tab.",
+        None => "Authorization failed. You can close this tab.",
     };
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+        message.len(),
+        message
+    );
+    stream.write_all(response.as_bytes())?;
+
+    code.ok_or(Error::Config("No authorization code received"))
+}
 
-    if response.status() == 200 {
-        let body = match response.text() {
-            Ok(body) => body,
-            Err(e) => panic!("Failed to read response body: {}", e),
-        };
-        let json: RefreshResponse = match serde_json::from_str(&body) {
-            Ok(json) => json,
-            Err(e) => panic!("Failed to parse JSON: {}", e),
-        };
+/// Pull a single query parameter out of an HTTP request target such as
+/// `/?code=abc&scope=read`.
+fn extract_query_param(path: &str, key: &str) -> Option<String> {
+    let url = Url::parse(&format!("http://localhost{}", path)).ok()?;
+    url.query_pairs()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn refresh_strava_token(api: &dyn StravaApi, config: &StravaConfig) -> Result<StravaConfig, Error> {
+    let json = api.refresh_token(config)?;
+    let new_config = config.with_tokens(json);
+    if let Some(path) = &new_config.token_file {
+        save_token_store(path, &new_config)?;
+    }
+    Ok(new_config)
+}
+
+/// Fetch a page of the authenticated athlete's activities. Rate-limit and
+/// other non-200 responses are surfaced as `Error::StravaApi` by `get`.
+fn get_activities(
+    api: &dyn StravaApi,
+    access_token: &str,
+    page: u32,
+    per_page: u32,
+) -> Result<Vec<Activity>, Error> {
+    let page = page.to_string();
+    let per_page = per_page.to_string();
+    let params = [("page", page.as_str()), ("per_page", per_page.as_str())];
+    let value = api.get("/api/v3/athlete/activities", access_token, &params)?;
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod is_token_stale_tests {
+    use super::*;
+
+    fn config_with(
+        access_token: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> StravaConfig {
         StravaConfig {
-            client_id: config.client_id,
-            client_secret: config.client_secret.clone(),
-            refresh_token: Some(json.refresh_token),
-            redirect_uri: config.redirect_uri.clone(),
-            access_token: Some(json.access_token),
-            strava_url: config.strava_url.clone(),
+            client_id: 123456,
+            client_secret: "dummy_secret".to_string(),
+            refresh_token: Some("dummy_token".to_string()),
+            redirect_uri: "http://localhost:8080/".to_string(),
+            access_token,
+            expires_at,
+            token_file: None,
+            strava_url: "https://www.strava.com".to_string(),
         }
-    } else {
-        panic!("Failed to refresh token: {}", response.status());
+    }
+
+    #[test]
+    fn test_fresh_token_is_not_stale() {
+        let config = config_with(
+            Some("dummy_access_token".to_string()),
+            Some(Utc::now() + Duration::hours(1)),
+        );
+        assert!(!config.is_token_stale());
+    }
+
+    #[test]
+    fn test_expired_token_is_stale() {
+        let config = config_with(
+            Some("dummy_access_token".to_string()),
+            Some(Utc::now() - Duration::hours(1)),
+        );
+        assert!(config.is_token_stale());
+    }
+
+    #[test]
+    fn test_token_within_margin_is_stale() {
+        let config = config_with(
+            Some("dummy_access_token".to_string()),
+            Some(Utc::now() + Duration::seconds(30)),
+        );
+        assert!(config.is_token_stale());
+    }
+
+    #[test]
+    fn test_missing_access_token_is_stale() {
+        let config = config_with(None, Some(Utc::now() + Duration::hours(1)));
+        assert!(config.is_token_stale());
+    }
+
+    #[test]
+    fn test_missing_expiry_is_stale() {
+        let config = config_with(Some("dummy_access_token".to_string()), None);
+        assert!(config.is_token_stale());
+    }
+}
+
+#[cfg(test)]
+mod extract_query_param_tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_query_param_present() {
+        assert_eq!(
+            extract_query_param("/?code=abc123&scope=read", "code"),
+            Some("abc123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_query_param_missing() {
+        assert_eq!(extract_query_param("/?scope=read", "code"), None);
     }
 }
 
@@ -142,8 +580,10 @@ mod load_env_variables_tests {
             client_id: 123456,
             client_secret: "dummy_secret".to_string(),
             refresh_token: Some("dummy_token".to_string()),
-            redirect_uri: "http://localhost/".to_string(),
+            redirect_uri: "http://localhost:8080/".to_string(),
             access_token: None,
+            expires_at: None,
+            token_file: None,
             strava_url: "https://www.strava.com".to_string(),
         };
 
@@ -159,7 +599,8 @@ mod load_env_variables_tests {
 
         match load_env_variables() {
             Ok(_) => panic!("Expected an Err because STRAVA_CLIENT_ID is not a number"),
-            Err(e) => assert_eq!(e, "Invalid STRAVA_CLIENT_ID"),
+            Err(Error::Config(msg)) => assert_eq!(msg, "Invalid STRAVA_CLIENT_ID"),
+            Err(e) => panic!("Expected a Config error, got: {}", e),
         }
     }
 
@@ -181,7 +622,8 @@ mod load_env_variables_tests {
             // Run the function and check that it returns the correct error
             match load_env_variables() {
                 Ok(_) => panic!("Expected an Err because one of the keys is not set"),
-                Err(e) => assert_eq!(e, *expected_error),
+                Err(Error::Config(msg)) => assert_eq!(msg, *expected_error),
+                Err(e) => panic!("Expected a Config error, got: {}", e),
             }
         }
     }
@@ -198,8 +640,10 @@ mod load_env_variables_tests {
             client_id: 123456,
             client_secret: "dummy_secret".to_string(),
             refresh_token: None,
-            redirect_uri: "http://localhost/".to_string(),
+            redirect_uri: "http://localhost:8080/".to_string(),
             access_token: None,
+            expires_at: None,
+            token_file: None,
             strava_url: "https://www.strava.com".to_string(),
         };
 
@@ -221,6 +665,8 @@ mod build_auth_url_tests {
             refresh_token: None,
             redirect_uri: "http://localhost/".to_string(),
             access_token: None,
+            expires_at: None,
+            token_file: None,
             strava_url: "https://www.strava.com".to_string(),
         };
 
@@ -233,6 +679,7 @@ mod build_auth_url_tests {
 #[cfg(test)]
 mod refresh_strava_token_tests {
     use super::*;
+    use chrono::TimeZone;
     use mockito::Matcher;
 
     #[test]
@@ -250,6 +697,8 @@ mod refresh_strava_token_tests {
             refresh_token: Some(refresh_token.clone()),
             redirect_uri: redirect_uri.clone(),
             access_token: None,
+            expires_at: None,
+            token_file: None,
             strava_url: server.url(),
         };
 
@@ -259,6 +708,8 @@ mod refresh_strava_token_tests {
             refresh_token: Some(refresh_token.clone()),
             redirect_uri: redirect_uri.clone(),
             access_token: Some("dummy_access_token".to_string()),
+            expires_at: Some(Utc.timestamp_opt(1700000000, 0).unwrap()),
+            token_file: None,
             strava_url: server.url(),
         };
 
@@ -268,9 +719,86 @@ mod refresh_strava_token_tests {
                 Matcher::AllOf(vec![Matcher::UrlEncoded("client_id".to_string(), "123456".to_string()), Matcher::UrlEncoded("client_secret".to_string(), "dummy_secret".to_string()), Matcher::UrlEncoded("refresh_token".to_string(), "dummy_token".to_string()), Matcher::UrlEncoded("grant_type".to_string(), "refresh_token".to_string())])
             )
             .with_status(200)
-            .with_body(r#"{"refresh_token":"dummy_token","access_token":"dummy_access_token","token_type":"Bearer","expires_in":21600}"#)
+            .with_body(r#"{"refresh_token":"dummy_token","access_token":"dummy_access_token","token_type":"Bearer","expires_in":21600,"expires_at":1700000000}"#)
             .create();
-        assert_eq!(refresh_strava_token(&config), expected);
+        let api = StravaImpl::new(&config);
+        assert_eq!(refresh_strava_token(&api, &config).unwrap(), expected);
         mock.assert();
     }
 }
+
+#[cfg(test)]
+mod get_activities_tests {
+    use super::*;
+
+    #[test]
+    fn test_get_activities() {
+        let mut server = mockito::Server::new();
+
+        let config = StravaConfig {
+            client_id: 123456,
+            client_secret: "dummy_secret".to_string(),
+            refresh_token: Some("dummy_token".to_string()),
+            redirect_uri: "http://localhost/".to_string(),
+            access_token: Some("dummy_access_token".to_string()),
+            expires_at: None,
+            token_file: None,
+            strava_url: server.url(),
+        };
+
+        let mock = server
+            .mock("GET", "/api/v3/athlete/activities")
+            .match_header("authorization", "Bearer dummy_access_token")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("page".to_string(), "1".to_string()),
+                mockito::Matcher::UrlEncoded("per_page".to_string(), "30".to_string()),
+            ]))
+            .with_status(200)
+            .with_body(
+                r#"[{"id":1,"name":"Morning Run","distance":5000.0,"moving_time":1500,"type":"Run","start_date":"2023-11-14T06:30:00Z"}]"#,
+            )
+            .create();
+
+        let api = StravaImpl::new(&config);
+        let activities = get_activities(&api, "dummy_access_token", 1, 30).unwrap();
+        mock.assert();
+
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0].id, 1);
+        assert_eq!(activities[0].name, "Morning Run");
+        assert_eq!(activities[0].activity_type, "Run");
+    }
+}
+
+#[cfg(test)]
+mod token_store_tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_save_then_load_token_store() {
+        let mut path = std::env::temp_dir();
+        path.push("mady_my_strava_token_store_test.json");
+        let path = path.to_str().unwrap().to_string();
+
+        let config = StravaConfig {
+            client_id: 123456,
+            client_secret: "dummy_secret".to_string(),
+            refresh_token: Some("rotated_token".to_string()),
+            redirect_uri: "http://localhost/".to_string(),
+            access_token: Some("dummy_access_token".to_string()),
+            expires_at: Some(Utc.timestamp_opt(1700000000, 0).unwrap()),
+            token_file: Some(path.clone()),
+            strava_url: "https://www.strava.com".to_string(),
+        };
+
+        save_token_store(&path, &config).unwrap();
+        let store = load_token_store(&path).unwrap();
+
+        assert_eq!(store.refresh_token, "rotated_token");
+        assert_eq!(store.access_token, "dummy_access_token");
+        assert_eq!(store.expires_at, Utc.timestamp_opt(1700000000, 0).unwrap());
+
+        std::fs::remove_file(&path).ok();
+    }
+}